@@ -0,0 +1,128 @@
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+
+use requiem::eval::{evaluate, truth_table, TruthTable};
+use requiem::lexer::{tokenize, LexingError};
+use requiem::parser::{parse, ParseError};
+
+fn main() {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    loop {
+        print!("> ");
+        stdout.flush().expect("failed to flush stdout");
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).expect("failed to read stdin") == 0 {
+            break;
+        }
+
+        let line = line.trim_end_matches('\n');
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Err(message) = run_line(line) {
+            println!("{message}");
+        }
+    }
+}
+
+/// Runs a single REPL line, which is either a bare expression (printing its
+/// truth table) or an expression followed by `| id=bool ...` assignments
+/// (printing the evaluated result for that assignment).
+fn run_line(line: &str) -> Result<(), String> {
+    let (expr_str, assignment_str) = match line.split_once('|') {
+        Some((expr, assignment)) => (expr, Some(assignment)),
+        None => (line, None),
+    };
+
+    let tokens = tokenize(expr_str).map_err(|err| render_lexing_error(expr_str, &err))?;
+    let expr = parse(tokens).map_err(|err| render_parse_error(expr_str, &err))?;
+
+    match assignment_str {
+        Some(assignment) => {
+            let inputs = parse_assignment(assignment)?;
+            let result = evaluate(&expr, &inputs).map_err(|err| err.to_string())?;
+            println!("{result}");
+        }
+        None => {
+            let table = truth_table(&expr).map_err(|err| err.to_string())?;
+            print_truth_table(&table);
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_assignment(input: &str) -> Result<Vec<bool>, String> {
+    let mut assignments = vec![];
+
+    for pair in input.split_whitespace() {
+        let (id, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected `id=bool`, found `{pair}`"))?;
+        let id: usize = id
+            .parse()
+            .map_err(|_| format!("`{id}` is not a valid terminal id"))?;
+        let value: bool = value
+            .parse()
+            .map_err(|_| format!("`{value}` is not `true` or `false`"))?;
+
+        assignments.push((id, value));
+    }
+
+    let width = assignments.iter().map(|(id, _)| id + 1).max().unwrap_or(0);
+    let mut inputs = vec![false; width];
+    for (id, value) in assignments {
+        inputs[id] = value;
+    }
+
+    Ok(inputs)
+}
+
+fn print_truth_table(table: &TruthTable) {
+    for (values, output) in &table.rows {
+        let assignment = values
+            .iter()
+            .zip(&table.terminals)
+            .map(|(value, id)| format!("{id}={value}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("{assignment} -> {output}");
+    }
+}
+
+fn render_lexing_error(input: &str, err: &LexingError) -> String {
+    let span = match err {
+        LexingError::ParenCountMismatch(span) => span.clone(),
+        LexingError::NoSuchGate(_, span) => span.clone(),
+        LexingError::ParseIntError(_, span) => span.clone(),
+        LexingError::Other(span) => span.clone(),
+    };
+
+    underline(input, &span, &err.to_string())
+}
+
+fn render_parse_error(input: &str, err: &ParseError) -> String {
+    let span = match err {
+        ParseError::MissingOperand(_, span) => span.clone(),
+        ParseError::MismatchedParens(span) => span.clone(),
+        ParseError::TrailingOperands(_, span) => span.clone(),
+        // No offending token to point at: nothing to underline.
+        ParseError::EmptyExpression | ParseError::Other => return err.to_string(),
+    };
+
+    underline(input, &span, &err.to_string())
+}
+
+fn underline(input: &str, span: &Range<usize>, message: &str) -> String {
+    let underline = format!(
+        "{}{}",
+        " ".repeat(span.start),
+        "^".repeat(span.len().max(1))
+    );
+
+    format!("{message}\n{input}\n{underline}")
+}
@@ -0,0 +1,194 @@
+use crate::lexer::{Gate, TerminalId};
+use crate::parser::Expr;
+
+/// Truth tables enumerate `2^n` rows for `n` distinct terminals, so beyond
+/// this many terminals the table would exhaust memory (or overflow a shift)
+/// long before it finished; [`truth_table`] reports an error instead.
+const MAX_TRUTH_TABLE_TERMINALS: usize = 24;
+
+#[derive(Clone, Debug, Default, PartialEq, thiserror::Error)]
+pub enum EvalError {
+    #[error("terminal {0} is out of range ({1} inputs provided)")]
+    OutOfRange(TerminalId, usize),
+    #[error("expression has {0} distinct terminals, which is more than the {MAX_TRUTH_TABLE_TERMINALS} a truth table can enumerate")]
+    TooManyTerminals(usize),
+    #[default]
+    #[error("evaluation error")]
+    Other,
+}
+
+/// Walks `expr`, looking up each [`Expr::Terminal`] in `inputs` by its
+/// [`TerminalId`] and folding gates according to their boolean semantics.
+pub fn evaluate(expr: &Expr, inputs: &[bool]) -> Result<bool, EvalError> {
+    match expr {
+        Expr::Terminal(id) => inputs
+            .get(*id as usize)
+            .copied()
+            .ok_or(EvalError::OutOfRange(*id, inputs.len())),
+        Expr::Not(inner) => evaluate(inner, inputs).map(|value| !value),
+        Expr::Binary(gate, lhs, rhs) => {
+            let lhs = evaluate(lhs, inputs)?;
+            let rhs = evaluate(rhs, inputs)?;
+
+            Ok(match gate {
+                Gate::And => lhs && rhs,
+                Gate::Or => lhs || rhs,
+                Gate::Nand => !(lhs && rhs),
+                Gate::Nor => !(lhs || rhs),
+                Gate::Xor => lhs ^ rhs,
+                Gate::Not => unreachable!("the parser never builds a Binary node with NOT"),
+            })
+        }
+    }
+}
+
+/// Every assignment of the distinct [`TerminalId`]s appearing in an
+/// expression, alongside the resulting output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TruthTable {
+    pub terminals: Vec<TerminalId>,
+    pub rows: Vec<(Vec<bool>, bool)>,
+}
+
+fn collect_terminals(expr: &Expr, ids: &mut Vec<TerminalId>) {
+    match expr {
+        Expr::Terminal(id) => {
+            if !ids.contains(id) {
+                ids.push(*id);
+            }
+        }
+        Expr::Not(inner) => collect_terminals(inner, ids),
+        Expr::Binary(_, lhs, rhs) => {
+            collect_terminals(lhs, ids);
+            collect_terminals(rhs, ids);
+        }
+    }
+}
+
+/// Enumerates all `2^n` assignments of the `n` distinct terminal IDs in
+/// `expr` and records the output for each one.
+pub fn truth_table(expr: &Expr) -> Result<TruthTable, EvalError> {
+    let mut terminals = vec![];
+    collect_terminals(expr, &mut terminals);
+    terminals.sort_unstable();
+
+    if terminals.len() > MAX_TRUTH_TABLE_TERMINALS {
+        return Err(EvalError::TooManyTerminals(terminals.len()));
+    }
+
+    let width = terminals.iter().copied().max().map_or(0, |id| id as usize + 1);
+    let row_count = 1u64 << terminals.len();
+    let mut rows = Vec::with_capacity(row_count as usize);
+
+    for assignment in 0..row_count {
+        let mut inputs = vec![false; width];
+        let mut values = Vec::with_capacity(terminals.len());
+
+        for (bit, &id) in terminals.iter().enumerate() {
+            let value = assignment & (1 << bit) != 0;
+            inputs[id as usize] = value;
+            values.push(value);
+        }
+
+        let output = evaluate(expr, &inputs).expect("all terminals are in range by construction");
+        rows.push((values, output));
+    }
+
+    Ok(TruthTable { terminals, rows })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+
+    fn eval_str(input: &str, inputs: &[bool]) -> bool {
+        let expr = parse(tokenize(input).unwrap()).unwrap();
+        evaluate(&expr, inputs).unwrap()
+    }
+
+    #[test]
+    fn test_and() {
+        assert!(eval_str("0 and 1", &[true, true]));
+        assert!(!eval_str("0 and 1", &[true, false]));
+    }
+
+    #[test]
+    fn test_or() {
+        assert!(eval_str("0 or 1", &[true, false]));
+        assert!(!eval_str("0 or 1", &[false, false]));
+    }
+
+    #[test]
+    fn test_not() {
+        assert!(eval_str("not 0", &[false]));
+        assert!(!eval_str("not 0", &[true]));
+    }
+
+    #[test]
+    fn test_nand() {
+        assert!(eval_str("0 nand 1", &[false, false]));
+        assert!(!eval_str("0 nand 1", &[true, true]));
+    }
+
+    #[test]
+    fn test_nor() {
+        assert!(eval_str("0 nor 1", &[false, false]));
+        assert!(!eval_str("0 nor 1", &[true, false]));
+    }
+
+    #[test]
+    fn test_xor() {
+        assert!(eval_str("0 xor 1", &[true, false]));
+        assert!(!eval_str("0 xor 1", &[true, true]));
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let expr = parse(tokenize("0 and 1").unwrap()).unwrap();
+        assert_eq!(evaluate(&expr, &[true]), Err(EvalError::OutOfRange(1, 1)));
+    }
+
+    #[test]
+    fn test_truth_table_and() {
+        let expr = parse(tokenize("0 and 1").unwrap()).unwrap();
+        let table = truth_table(&expr).unwrap();
+
+        assert_eq!(table.terminals, vec![0, 1]);
+        assert_eq!(
+            table.rows,
+            vec![
+                (vec![false, false], false),
+                (vec![true, false], false),
+                (vec![false, true], false),
+                (vec![true, true], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truth_table_reuses_terminal() {
+        // `0 xor 0` only has one distinct terminal, so the table has two
+        // rows, not four.
+        let expr = parse(tokenize("0 xor 0").unwrap()).unwrap();
+        let table = truth_table(&expr).unwrap();
+
+        assert_eq!(table.terminals, vec![0]);
+        assert_eq!(table.rows, vec![(vec![false], false), (vec![true], false)]);
+    }
+
+    #[test]
+    fn test_truth_table_rejects_too_many_terminals() {
+        let input = (0..=MAX_TRUTH_TABLE_TERMINALS)
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(" or ");
+        let expr = parse(tokenize(&input).unwrap()).unwrap();
+
+        assert_eq!(
+            truth_table(&expr),
+            Err(EvalError::TooManyTerminals(MAX_TRUTH_TABLE_TERMINALS + 1))
+        );
+    }
+}
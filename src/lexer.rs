@@ -1,21 +1,27 @@
 use logos::Logos;
+use std::ops::Range;
 use std::str::FromStr;
 use strum::{Display, EnumString};
 
-#[derive(Clone, Debug, Default, PartialEq, thiserror::Error)]
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
 pub enum LexingError {
-    #[error("Mismatching parentheses count")]
-    ParenCountMismatch,
-    #[error("{0} is not a valid logic gate")]
-    NoSuchGate(String),
-    #[error(transparent)]
-    ParseIntError(#[from] std::num::ParseIntError),
-    #[default]
-    #[error("Invalid token")]
-    Other,
+    #[error("mismatching parentheses at {0:?}")]
+    ParenCountMismatch(Range<usize>),
+    #[error("{0} is not a valid logic gate at {1:?}")]
+    NoSuchGate(String, Range<usize>),
+    #[error("{0} at {1:?}")]
+    ParseIntError(std::num::ParseIntError, Range<usize>),
+    #[error("invalid token at {0:?}")]
+    Other(Range<usize>),
 }
 
-#[derive(Debug, EnumString, PartialEq, Display)]
+impl Default for LexingError {
+    fn default() -> Self {
+        LexingError::Other(0..0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, EnumString, PartialEq, Display)]
 #[strum(serialize_all = "UPPERCASE")]
 pub enum Gate {
     And,
@@ -26,9 +32,9 @@ pub enum Gate {
     Xor,
 }
 
-type TerminalId = u16;
+pub(crate) type TerminalId = u16;
 
-#[derive(Debug, Logos, PartialEq, Display)]
+#[derive(Clone, Debug, Logos, PartialEq, Display)]
 #[logos(error = LexingError)]
 #[logos(skip r"[ \t\n\f]+")]
 pub enum Token {
@@ -40,7 +46,10 @@ pub enum Token {
 
     #[regex(
         "[0-9]+",
-        |lex| TerminalId::from_str_radix(lex.slice(), 10),
+        |lex| {
+            TerminalId::from_str_radix(lex.slice(), 10)
+                .map_err(|err| LexingError::ParseIntError(err, lex.span()))
+        },
         priority = 2
     )]
     TerminalId(TerminalId),
@@ -50,31 +59,157 @@ pub enum Token {
         |lex| {
             let gate_str = lex.slice().to_uppercase();
             Gate::from_str(&gate_str)
-                .map_err(|_| LexingError::NoSuchGate(gate_str.to_string()))
+                .map_err(|_| LexingError::NoSuchGate(gate_str.to_string(), lex.span()))
         }
     )]
     Gate(Gate),
 }
 
-pub fn tokenize(input: &str) -> Result<Vec<Token>, LexingError> {
-    let lex = Token::lexer(input);
+/// A token paired with the byte range in the source it was lexed from, so
+/// that diagnostics and incremental re-lexing can point back at the input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub token: T,
+    pub span: Range<usize>,
+}
+
+/// Lexes `input` into tokens without checking parenthesis balance. Used
+/// both by [`tokenize`] (which checks the whole input) and by [`retokenize`]
+/// (which only re-lexes part of a larger, already-balanced token stream and
+/// checks balance over the merged result instead).
+fn lex_tokens(input: &str) -> Result<Vec<Spanned<Token>>, LexingError> {
+    let lex = Token::lexer(input).spanned();
     let mut tokens = vec![];
-    let (mut open, mut close) = (0, 0);
 
-    for token in lex {
-        match token {
-            Ok(Token::ParenOpen) => open += 1,
-            Ok(Token::ParenClose) => close += 1,
+    for (token, span) in lex {
+        let token = token.map_err(|err| match err {
+            LexingError::Other(_) => LexingError::Other(span.clone()),
+            err => err,
+        })?;
+
+        tokens.push(Spanned { token, span });
+    }
+
+    Ok(tokens)
+}
+
+fn check_paren_balance(tokens: &[Spanned<Token>]) -> Result<(), LexingError> {
+    let mut open_parens: Vec<Range<usize>> = vec![];
+
+    for spanned in tokens {
+        match &spanned.token {
+            Token::ParenOpen => open_parens.push(spanned.span.clone()),
+            Token::ParenClose if open_parens.pop().is_none() => {
+                return Err(LexingError::ParenCountMismatch(spanned.span.clone()));
+            }
             _ => {}
-        };
+        }
+    }
 
-        tokens.push(token?)
+    if let Some(unmatched) = open_parens.into_iter().next() {
+        return Err(LexingError::ParenCountMismatch(unmatched));
     }
 
-    if open != close {
-        return Err(LexingError::ParenCountMismatch);
+    Ok(())
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Spanned<Token>>, LexingError> {
+    let tokens = lex_tokens(input)?;
+    check_paren_balance(&tokens)?;
+
+    Ok(tokens)
+}
+
+/// A single edit to a source string: the byte `range` that was replaced,
+/// and the `replacement` text that now occupies it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+fn shift_span(span: Range<usize>, delta: isize) -> Range<usize> {
+    let shift = |offset: usize| (offset as isize + delta) as usize;
+    shift(span.start)..shift(span.end)
+}
+
+/// Shifts the span carried by a [`LexingError`] by `offset`, so that an
+/// error produced while lexing a substring can be reported relative to the
+/// full source it was sliced from.
+fn offset_lexing_error(err: LexingError, offset: usize) -> LexingError {
+    let offset = offset as isize;
+
+    match err {
+        LexingError::ParenCountMismatch(span) => {
+            LexingError::ParenCountMismatch(shift_span(span, offset))
+        }
+        LexingError::NoSuchGate(gate, span) => {
+            LexingError::NoSuchGate(gate, shift_span(span, offset))
+        }
+        LexingError::ParseIntError(err, span) => {
+            LexingError::ParseIntError(err, shift_span(span, offset))
+        }
+        LexingError::Other(span) => LexingError::Other(shift_span(span, offset)),
+    }
+}
+
+/// Re-tokenizes only the region of `new_source` affected by `edit`, instead
+/// of re-lexing the whole string. `previous` is the token stream lexed from
+/// the source *before* the edit was applied, and `new_source` is the source
+/// *after* it.
+///
+/// Finds the widest run of `previous` tokens whose spans touch `edit.range`,
+/// re-lexes just that (token-aligned) region of `new_source`, shifts every
+/// untouched token after it by the edit's length delta, and splices the
+/// pieces back together. Because this grammar has no multi-line or
+/// nested-comment tokens, token boundaries are stable, so the only
+/// invariant that needs rechecking is parenthesis balance over the merged
+/// result.
+pub fn retokenize(
+    new_source: &str,
+    previous: &[Spanned<Token>],
+    edit: &Edit,
+) -> Result<Vec<Spanned<Token>>, LexingError> {
+    let delta = edit.replacement.len() as isize - (edit.range.end - edit.range.start) as isize;
+
+    let left = previous
+        .iter()
+        .position(|spanned| spanned.span.end >= edit.range.start)
+        .unwrap_or(previous.len());
+
+    let mut right = left;
+    while right < previous.len() && previous[right].span.start <= edit.range.end {
+        right += 1;
     }
 
+    let old_start = previous
+        .get(left)
+        .map_or(edit.range.start, |spanned| spanned.span.start.min(edit.range.start));
+    let old_end = if right > left {
+        previous[right - 1].span.end.max(edit.range.end)
+    } else {
+        edit.range.end
+    };
+    let new_end = (old_end as isize + delta) as usize;
+
+    let relexed = lex_tokens(&new_source[old_start..new_end])
+        .map_err(|err| offset_lexing_error(err, old_start))?
+        .into_iter()
+        .map(|spanned| Spanned {
+            token: spanned.token,
+            span: (spanned.span.start + old_start)..(spanned.span.end + old_start),
+        });
+
+    let mut tokens = Vec::with_capacity(previous.len());
+    tokens.extend(previous[..left].iter().cloned());
+    tokens.extend(relexed);
+    tokens.extend(previous[right..].iter().cloned().map(|spanned| Spanned {
+        token: spanned.token,
+        span: shift_span(spanned.span, delta),
+    }));
+
+    check_paren_balance(&tokens)?;
+
     Ok(tokens)
 }
 
@@ -82,6 +217,14 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, LexingError> {
 mod test {
     use super::*;
 
+    fn tokens_of(input: &str) -> Vec<Token> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect()
+    }
+
     #[test]
     fn test_empty() {
         let tokens = tokenize("").unwrap();
@@ -90,40 +233,41 @@ mod test {
 
     #[test]
     fn test_paren_count_mismatch() {
-        let err = Err(LexingError::ParenCountMismatch);
+        assert_eq!(tokenize("("), Err(LexingError::ParenCountMismatch(0..1)));
+        assert_eq!(tokenize(")"), Err(LexingError::ParenCountMismatch(0..1)));
+    }
 
-        assert_eq!(tokenize("("), err);
-        assert_eq!(tokenize(")"), err);
+    #[test]
+    fn test_paren_count_mismatch_reports_first_unmatched() {
+        // The second `(` is closed by the `)`; the first one never is.
+        assert_eq!(
+            tokenize("((0)"),
+            Err(LexingError::ParenCountMismatch(0..1))
+        );
     }
 
     #[test]
     fn test_terminal_id() {
-        let tokens = tokenize("0").unwrap();
-        assert_eq!(tokens, vec![Token::TerminalId(0)]);
-
-        let tokens = tokenize("69").unwrap();
-        assert_eq!(tokens, vec![Token::TerminalId(69)]);
+        assert_eq!(tokens_of("0"), vec![Token::TerminalId(0)]);
+        assert_eq!(tokens_of("69"), vec![Token::TerminalId(69)]);
     }
 
     #[test]
     fn test_gate() {
-        let tokens = tokenize("and").unwrap();
-        assert_eq!(tokens, vec![Token::Gate(Gate::And)]);
-
-        let tokens = tokenize("or").unwrap();
-        assert_eq!(tokens, vec![Token::Gate(Gate::Or)]);
-
-        let tokens = tokenize("not").unwrap();
-        assert_eq!(tokens, vec![Token::Gate(Gate::Not)]);
-
-        let tokens = tokenize("nand").unwrap();
-        assert_eq!(tokens, vec![Token::Gate(Gate::Nand)]);
-
-        let tokens = tokenize("nor").unwrap();
-        assert_eq!(tokens, vec![Token::Gate(Gate::Nor)]);
+        assert_eq!(tokens_of("and"), vec![Token::Gate(Gate::And)]);
+        assert_eq!(tokens_of("or"), vec![Token::Gate(Gate::Or)]);
+        assert_eq!(tokens_of("not"), vec![Token::Gate(Gate::Not)]);
+        assert_eq!(tokens_of("nand"), vec![Token::Gate(Gate::Nand)]);
+        assert_eq!(tokens_of("nor"), vec![Token::Gate(Gate::Nor)]);
+        assert_eq!(tokens_of("xor"), vec![Token::Gate(Gate::Xor)]);
+    }
 
-        let tokens = tokenize("xor").unwrap();
-        assert_eq!(tokens, vec![Token::Gate(Gate::Xor)]);
+    #[test]
+    fn test_no_such_gate_span() {
+        assert_eq!(
+            tokenize("xyz"),
+            Err(LexingError::NoSuchGate("XYZ".to_string(), 0..3))
+        );
     }
 
     #[test]
@@ -132,24 +276,107 @@ mod test {
         assert_eq!(
             tokens,
             vec![
-                Token::ParenOpen,
-                Token::TerminalId(0),
-                Token::Gate(Gate::And),
-                Token::TerminalId(1),
-                Token::ParenClose
+                Spanned {
+                    token: Token::ParenOpen,
+                    span: 0..1
+                },
+                Spanned {
+                    token: Token::TerminalId(0),
+                    span: 1..2
+                },
+                Spanned {
+                    token: Token::Gate(Gate::And),
+                    span: 3..6
+                },
+                Spanned {
+                    token: Token::TerminalId(1),
+                    span: 7..8
+                },
+                Spanned {
+                    token: Token::ParenClose,
+                    span: 8..9
+                },
             ]
         );
+    }
+
+    #[test]
+    fn test_retokenize_matches_full_relex_on_terminal_edit() {
+        let source = "0 and 1";
+        let previous = tokenize(source).unwrap();
+        let edit = Edit {
+            range: 0..1,
+            replacement: "12".to_string(),
+        };
+        let new_source = "12 and 1";
 
-        let tokens = tokenize("(0 and 1)").unwrap();
         assert_eq!(
-            tokens,
-            vec![
-                Token::ParenOpen,
-                Token::TerminalId(0),
-                Token::Gate(Gate::And),
-                Token::TerminalId(1),
-                Token::ParenClose
-            ]
+            retokenize(new_source, &previous, &edit).unwrap(),
+            tokenize(new_source).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_retokenize_matches_full_relex_on_gate_edit() {
+        let source = "0 and 1";
+        let previous = tokenize(source).unwrap();
+        let edit = Edit {
+            range: 2..5,
+            replacement: "or".to_string(),
+        };
+        let new_source = "0 or 1";
+
+        assert_eq!(
+            retokenize(new_source, &previous, &edit).unwrap(),
+            tokenize(new_source).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_retokenize_matches_full_relex_on_append() {
+        let source = "0 and 1";
+        let previous = tokenize(source).unwrap();
+        let edit = Edit {
+            range: 7..7,
+            replacement: " and 2".to_string(),
+        };
+        let new_source = "0 and 1 and 2";
+
+        assert_eq!(
+            retokenize(new_source, &previous, &edit).unwrap(),
+            tokenize(new_source).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_retokenize_rechecks_paren_balance() {
+        let source = "(0 and 1)";
+        let previous = tokenize(source).unwrap();
+        let edit = Edit {
+            range: 8..9,
+            replacement: String::new(),
+        };
+        let new_source = "(0 and 1";
+
+        assert_eq!(
+            retokenize(new_source, &previous, &edit),
+            Err(LexingError::ParenCountMismatch(0..1))
+        );
+    }
+
+    #[test]
+    fn test_retokenize_error_span_is_relative_to_full_source() {
+        let source = "2 ";
+        let previous = tokenize(source).unwrap();
+        let edit = Edit {
+            range: 2..2,
+            replacement: "d3".to_string(),
+        };
+        let new_source = "2 d3";
+
+        assert_eq!(
+            retokenize(new_source, &previous, &edit),
+            tokenize(new_source)
         );
     }
 }
@@ -0,0 +1,3 @@
+pub mod eval;
+pub mod lexer;
+pub mod parser;
@@ -0,0 +1,245 @@
+use std::ops::Range;
+
+use crate::lexer::{Gate, Spanned, TerminalId, Token};
+
+#[derive(Clone, Debug, Default, PartialEq, thiserror::Error)]
+pub enum ParseError {
+    #[error("empty expression")]
+    EmptyExpression,
+    #[error("missing operand for {0} at {1:?}")]
+    MissingOperand(Gate, Range<usize>),
+    #[error("mismatched parentheses at {0:?}")]
+    MismatchedParens(Range<usize>),
+    #[error("expected a single expression, found {0} leftover values starting at {1:?}")]
+    TrailingOperands(usize, Range<usize>),
+    #[default]
+    #[error("invalid expression")]
+    Other,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Terminal(TerminalId),
+    Not(Box<Expr>),
+    Binary(Gate, Box<Expr>, Box<Expr>),
+}
+
+enum StackOp {
+    ParenOpen(Range<usize>),
+    Gate(Gate, Range<usize>),
+}
+
+fn precedence(gate: Gate) -> u8 {
+    match gate {
+        Gate::Not => 4,
+        Gate::And | Gate::Nand => 3,
+        Gate::Xor => 2,
+        Gate::Or | Gate::Nor => 1,
+    }
+}
+
+fn is_right_associative(gate: Gate) -> bool {
+    matches!(gate, Gate::Not)
+}
+
+fn fold(
+    output: &mut Vec<(Expr, Range<usize>)>,
+    gate: Gate,
+    gate_span: Range<usize>,
+) -> Result<(), ParseError> {
+    if gate == Gate::Not {
+        let (operand, operand_span) = output
+            .pop()
+            .ok_or_else(|| ParseError::MissingOperand(gate, gate_span.clone()))?;
+        let span = gate_span.start..operand_span.end;
+        output.push((Expr::Not(Box::new(operand)), span));
+    } else {
+        let (rhs, rhs_span) = output
+            .pop()
+            .ok_or_else(|| ParseError::MissingOperand(gate, gate_span.clone()))?;
+        let (lhs, lhs_span) = output
+            .pop()
+            .ok_or_else(|| ParseError::MissingOperand(gate, gate_span.clone()))?;
+        let span = lhs_span.start..rhs_span.end;
+        output.push((Expr::Binary(gate, Box::new(lhs), Box::new(rhs)), span));
+    }
+
+    Ok(())
+}
+
+/// Parses a token stream into an [`Expr`] using Dijkstra's shunting-yard
+/// algorithm, so that gate precedence (`NOT` > `AND`/`NAND` > `XOR` >
+/// `OR`/`NOR`) disambiguates expressions without requiring parentheses.
+/// Every [`ParseError`] variant other than [`ParseError::EmptyExpression`]
+/// and [`ParseError::Other`] carries a span: that of the offending token,
+/// or for [`ParseError::TrailingOperands`] that of the first leftover value.
+pub fn parse(tokens: Vec<Spanned<Token>>) -> Result<Expr, ParseError> {
+    let mut output: Vec<(Expr, Range<usize>)> = vec![];
+    let mut operators: Vec<StackOp> = vec![];
+
+    for Spanned { token, span } in tokens {
+        match token {
+            Token::TerminalId(id) => output.push((Expr::Terminal(id), span)),
+            Token::ParenOpen => operators.push(StackOp::ParenOpen(span)),
+            Token::ParenClose => loop {
+                match operators.pop() {
+                    Some(StackOp::ParenOpen(_)) => break,
+                    Some(StackOp::Gate(gate, gate_span)) => fold(&mut output, gate, gate_span)?,
+                    None => return Err(ParseError::MismatchedParens(span)),
+                }
+            },
+            Token::Gate(gate) => {
+                while let Some(StackOp::Gate(top, _)) = operators.last() {
+                    let top = *top;
+                    let should_fold = precedence(top) > precedence(gate)
+                        || (precedence(top) == precedence(gate) && !is_right_associative(gate));
+
+                    if !should_fold {
+                        break;
+                    }
+
+                    let Some(StackOp::Gate(top, top_span)) = operators.pop() else {
+                        unreachable!("just matched StackOp::Gate above");
+                    };
+                    fold(&mut output, top, top_span)?;
+                }
+
+                operators.push(StackOp::Gate(gate, span));
+            }
+        }
+    }
+
+    while let Some(op) = operators.pop() {
+        match op {
+            StackOp::ParenOpen(span) => return Err(ParseError::MismatchedParens(span)),
+            StackOp::Gate(gate, gate_span) => fold(&mut output, gate, gate_span)?,
+        }
+    }
+
+    match output.len() {
+        0 => Err(ParseError::EmptyExpression),
+        1 => Ok(output.pop().expect("checked non-empty above").0),
+        n => {
+            let leftover_span = output[1].1.clone();
+            Err(ParseError::TrailingOperands(n - 1, leftover_span))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    fn parse_str(input: &str) -> Expr {
+        parse(tokenize(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_single_terminal() {
+        assert_eq!(parse_str("0"), Expr::Terminal(0));
+    }
+
+    #[test]
+    fn test_not_is_unary() {
+        assert_eq!(
+            parse_str("not 0"),
+            Expr::Not(Box::new(Expr::Terminal(0)))
+        );
+    }
+
+    #[test]
+    fn test_double_not() {
+        assert_eq!(
+            parse_str("not not 0"),
+            Expr::Not(Box::new(Expr::Not(Box::new(Expr::Terminal(0)))))
+        );
+    }
+
+    #[test]
+    fn test_and_or_precedence() {
+        // `0 and 1 or 2` should parse as `(0 and 1) or 2`, not `0 and (1 or 2)`.
+        assert_eq!(
+            parse_str("0 and 1 or 2"),
+            Expr::Binary(
+                Gate::Or,
+                Box::new(Expr::Binary(
+                    Gate::And,
+                    Box::new(Expr::Terminal(0)),
+                    Box::new(Expr::Terminal(1))
+                )),
+                Box::new(Expr::Terminal(2))
+            )
+        );
+    }
+
+    #[test]
+    fn test_not_binds_tighter_than_and() {
+        assert_eq!(
+            parse_str("not 0 and 1"),
+            Expr::Binary(
+                Gate::And,
+                Box::new(Expr::Not(Box::new(Expr::Terminal(0)))),
+                Box::new(Expr::Terminal(1))
+            )
+        );
+    }
+
+    #[test]
+    fn test_explicit_parens_override_precedence() {
+        assert_eq!(
+            parse_str("0 and (1 or 2)"),
+            Expr::Binary(
+                Gate::And,
+                Box::new(Expr::Terminal(0)),
+                Box::new(Expr::Binary(
+                    Gate::Or,
+                    Box::new(Expr::Terminal(1)),
+                    Box::new(Expr::Terminal(2))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn test_mismatched_parens_stray_close() {
+        // `tokenize` already rejects unbalanced parens, so exercise the
+        // parser's own defense-in-depth check with a hand-built token
+        // stream instead.
+        let tokens = vec![Spanned {
+            token: Token::ParenClose,
+            span: 0..1,
+        }];
+        assert_eq!(parse(tokens), Err(ParseError::MismatchedParens(0..1)));
+    }
+
+    #[test]
+    fn test_mismatched_parens_leftover_open() {
+        let tokens = vec![
+            Spanned {
+                token: Token::ParenOpen,
+                span: 0..1,
+            },
+            Spanned {
+                token: Token::TerminalId(0),
+                span: 1..2,
+            },
+        ];
+        assert_eq!(parse(tokens), Err(ParseError::MismatchedParens(0..1)));
+    }
+
+    #[test]
+    fn test_missing_operand() {
+        let tokens = tokenize("and 0").unwrap();
+        assert_eq!(
+            parse(tokens),
+            Err(ParseError::MissingOperand(Gate::And, 0..3))
+        );
+    }
+
+    #[test]
+    fn test_trailing_operands() {
+        let tokens = tokenize("0 1").unwrap();
+        assert_eq!(parse(tokens), Err(ParseError::TrailingOperands(1, 2..3)));
+    }
+}